@@ -1,8 +1,17 @@
-use crate::lexer::token::TokenType;
+use super::symbol::Symbol;
+use crate::lexer::token::{Span, TokenType};
+
+/// Wraps an AST node with the source span it was parsed from, so the
+/// evaluator can report where an error occurred.
+#[derive(Debug, Clone)]
+pub struct Node<T> {
+    pub inner: T,
+    pub span: Span,
+}
 
 #[derive(Debug, Clone)]
 pub enum ASTNode {
-    Program(Box<Vec<ASTNode>>),
+    Program(Box<Vec<Node<ASTNode>>>),
 
     FunctionExpression(FunctionExpression),
     FunctionCall(FunctionCall),
@@ -13,6 +22,7 @@ pub enum ASTNode {
     UnaryExpression(Box<ASTNode>),
 
     IfStatement(IfStatement),
+    WhileStatement(WhileStatement),
     BlockStatement(BlockStatement),
 
     Variable(String),
@@ -21,6 +31,7 @@ pub enum ASTNode {
     String(String),
 
     Command(Box<Vec<ASTNode>>),
+    Redirect(Box<ASTNode>),
 }
 
 #[derive(Debug, Clone)]
@@ -33,13 +44,13 @@ pub struct BinaryExpression {
 #[derive(Debug, Clone)]
 pub struct VariableExpression {
     pub name: String,
-    pub rhs: Box<ASTNode>,
+    pub value: Box<ASTNode>,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionExpression {
     pub name: String,
-    pub body: Box<ASTNode>,
+    pub body: Box<Vec<Node<ASTNode>>>,
     pub args: Vec<String>,
 }
 
@@ -52,7 +63,7 @@ impl PartialEq for FunctionExpression {
 #[derive(Debug, Clone)]
 pub struct FunctionCall {
     pub name: String,
-    pub args: Vec<ASTNode>,
+    pub args: Vec<Symbol>,
 }
 
 #[derive(Debug, Clone)]
@@ -62,7 +73,13 @@ pub struct IfStatement {
     pub alternative: Option<Box<ASTNode>>,
 }
 
+#[derive(Debug, Clone)]
+pub struct WhileStatement {
+    pub condition: Box<ASTNode>,
+    pub body: Box<BlockStatement>,
+}
+
 #[derive(Debug, Clone)]
 pub struct BlockStatement {
-    pub body: Box<Vec<ASTNode>>,
+    pub body: Box<Vec<Node<ASTNode>>>,
 }
@@ -1,10 +1,114 @@
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::process::{Command as ProcessCommand, Stdio};
+
 use super::ast::{
-    ASTNode, BinaryExpression, FunctionCall, FunctionExpression, IfStatement, VariableExpression,
+    ASTNode, BinaryExpression, BlockStatement, FunctionCall, FunctionExpression, IfStatement,
+    Node, VariableExpression, WhileStatement,
 };
 use super::symbol::Symbol;
 use super::symbol_table::SymbolTable;
+use crate::error::OrcaError;
 use crate::lexer::TokenType;
 
+/// A single stage of a command pipeline: the words it was invoked with and,
+/// if it redirected its output, the file it was redirected to.
+struct CommandStage {
+    argv: Vec<String>,
+    redirect: Option<String>,
+}
+
+/// Signals unwinding the evaluator's call stack: either a real error, or a
+/// `return` value bubbling up from a nested block to its enclosing function.
+/// Reusing the `Result` error channel for control flow avoids threading a
+/// separate "did we return" flag through every `eval_*` call site.
+enum Unwind {
+    Error(OrcaError),
+    Return(Option<Symbol>),
+}
+
+impl From<OrcaError> for Unwind {
+    fn from(err: OrcaError) -> Unwind {
+        Unwind::Error(err)
+    }
+}
+
+/// A native function callable from orca source, taking already-evaluated
+/// arguments and optionally producing a result symbol.
+type NativeFn = fn(&[Symbol]) -> Option<Symbol>;
+
+/// The standard library: native callables checked ahead of the symbol table
+/// so they can't be shadowed by a user-defined function of the same name.
+const BUILTINS: &[(&str, NativeFn)] = &[
+    ("print", native_print),
+    ("println", native_println),
+    ("len", native_len),
+    ("abs", native_abs),
+    ("sqrt", native_sqrt),
+    ("input", native_input),
+];
+
+fn symbol_to_display(symbol: &Symbol) -> String {
+    match symbol {
+        Symbol::Number(n) => n.to_string(),
+        Symbol::Boolean(b) => b.to_string(),
+        Symbol::String(s) => s.clone(),
+        Symbol::Variable(name) => name.clone(),
+        Symbol::Function(f) => format!("<function {}>", f.name),
+    }
+}
+
+fn native_print(args: &[Symbol]) -> Option<Symbol> {
+    print!(
+        "{}",
+        args.iter()
+            .map(symbol_to_display)
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    None
+}
+
+fn native_println(args: &[Symbol]) -> Option<Symbol> {
+    println!(
+        "{}",
+        args.iter()
+            .map(symbol_to_display)
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    None
+}
+
+fn native_len(args: &[Symbol]) -> Option<Symbol> {
+    match args.first()? {
+        Symbol::String(s) => Some(Symbol::Number(s.chars().count() as f64)),
+        _ => None,
+    }
+}
+
+fn native_abs(args: &[Symbol]) -> Option<Symbol> {
+    match args.first()? {
+        Symbol::Number(n) => Some(Symbol::Number(n.abs())),
+        _ => None,
+    }
+}
+
+fn native_sqrt(args: &[Symbol]) -> Option<Symbol> {
+    match args.first()? {
+        Symbol::Number(n) => Some(Symbol::Number(n.sqrt())),
+        _ => None,
+    }
+}
+
+fn native_input(_args: &[Symbol]) -> Option<Symbol> {
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line).ok()?;
+    Some(Symbol::String(
+        line.trim_end_matches(['\n', '\r']).to_string(),
+    ))
+}
+
 pub struct ASTEvaluator {
     symbol_table: SymbolTable,
 }
@@ -16,146 +120,337 @@ impl ASTEvaluator {
         }
     }
 
-    pub fn eval(&mut self, program: ASTNode) -> Vec<Option<Symbol>> {
+    pub fn eval(&mut self, program: ASTNode) -> Result<Vec<Option<Symbol>>, OrcaError> {
         let mut result = vec![];
         match program {
             ASTNode::Program(root) => {
-                for line in *root {
-                    result.push(self.eval_node(line));
+                for node in *root {
+                    let value = match self.eval_node(node.inner) {
+                        Ok(value) => value,
+                        Err(Unwind::Return(value)) => value,
+                        Err(Unwind::Error(err)) => return Err(err.with_span(node.span)),
+                    };
+                    result.push(value);
                 }
-                result
+                Ok(result)
             }
-            _ => result,
+            _ => Ok(result),
         }
     }
 
-    fn eval_statement_list(&mut self, statement_list: Vec<ASTNode>) {
+    /// Evaluates each statement, attaching its own span to any error that
+    /// doesn't already carry one closer to the fault, so a failure nested
+    /// inside a function/`if`/`while` body is blamed on the statement that
+    /// raised it rather than whatever top-level statement called into it.
+    fn eval_statement_list(&mut self, statement_list: Vec<Node<ASTNode>>) -> Result<(), Unwind> {
         for node in statement_list {
-            self.eval_node(node);
+            let span = node.span;
+            self.eval_node(node.inner).map_err(|unwind| match unwind {
+                Unwind::Error(err) => Unwind::Error(err.with_span(span)),
+                ret @ Unwind::Return(_) => ret,
+            })?;
         }
+        Ok(())
     }
 
-    fn eval_node(&mut self, node: ASTNode) -> Option<Symbol> {
+    fn eval_node(&mut self, node: ASTNode) -> Result<Option<Symbol>, Unwind> {
         match node {
-            ASTNode::Number(value) => Some(Symbol::Number(value)),
+            ASTNode::Number(value) => Ok(Some(Symbol::Number(value))),
             ASTNode::BinaryExpression(be) => self.eval_binary_expression(be),
             ASTNode::UnaryExpression(n) => self.eval_unary_expression(*n),
             ASTNode::VariableExpression(ve) => {
-                self.eval_variable_statement(ve);
-                None
+                self.eval_variable_statement(ve)?;
+                Ok(None)
             }
-            ASTNode::Variable(name) => Some(self.symbol_table.get(&name).clone()),
+            ASTNode::Variable(name) => Ok(Some(self.symbol_table.get(&name)?.clone())),
             ASTNode::FunctionExpression(fe) => {
                 self.symbol_table
                     .insert(&fe.name, Symbol::Function(fe.clone()));
-                None
+                Ok(None)
             }
             ASTNode::FunctionCall(fc) => self.eval_function_call(fc),
             ASTNode::IfStatement(is) => {
-                self.eval_if_statement(is);
-                None
+                self.eval_if_statement(is)?;
+                Ok(None)
+            }
+            ASTNode::WhileStatement(ws) => {
+                self.eval_while_statement(ws)?;
+                Ok(None)
+            }
+            ASTNode::ReturnExpression(expr) => {
+                let value = self.eval_node(*expr)?;
+                Err(Unwind::Return(value))
             }
-            _ => None,
+            ASTNode::Command(words) => self.eval_command(*words),
+            ASTNode::String(s) => Ok(Some(Symbol::String(s))),
+            ASTNode::Boolean(b) => Ok(Some(Symbol::Boolean(b))),
+            _ => Ok(None),
         }
     }
 
-    fn eval_if_statement(&mut self, if_statement: IfStatement) {
-        let passed = match self.eval_node(*if_statement.condition) {
-            Some(sym) => match sym {
-                Symbol::Number(num) => num != 0.0,
-                Symbol::Boolean(b) => b,
-                _ => false,
-            },
-            None => false,
-        };
+    fn is_truthy(&self, value: Option<Symbol>) -> bool {
+        match value {
+            Some(Symbol::Number(num)) => num != 0.0,
+            Some(Symbol::Boolean(b)) => b,
+            _ => false,
+        }
+    }
+
+    /// `if`/`else` bodies share the enclosing scope rather than pushing their
+    /// own: this language has no block-scoping story, only function scoping,
+    /// so an assignment inside a branch must still be visible once it ends.
+    fn eval_if_statement(&mut self, if_statement: IfStatement) -> Result<(), Unwind> {
+        let condition = self.eval_node(*if_statement.condition)?;
+        let passed = self.is_truthy(condition);
 
         if passed {
-            self.symbol_table.push_scope("if");
-            self.eval_statement_list(*if_statement.consequence);
-            self.symbol_table.pop_scope();
+            self.eval_block(*if_statement.consequence)?;
+        } else if let Some(alternative) = if_statement.alternative {
+            self.eval_alternative(*alternative)?;
+        }
+
+        Ok(())
+    }
+
+    fn eval_alternative(&mut self, alternative: ASTNode) -> Result<(), Unwind> {
+        match alternative {
+            ASTNode::IfStatement(is) => self.eval_if_statement(is),
+            block => self.eval_block(block),
+        }
+    }
+
+    /// Like `if`/`else`, the loop body shares the enclosing scope instead of
+    /// pushing its own, so a mutation such as `i = i + 1` updates the binding
+    /// the condition re-reads next iteration rather than one that's popped
+    /// before the next check.
+    fn eval_while_statement(&mut self, while_statement: WhileStatement) -> Result<(), Unwind> {
+        loop {
+            let condition = self.eval_node((*while_statement.condition).clone())?;
+            if !self.is_truthy(condition) {
+                break;
+            }
+
+            self.eval_statement_list(*while_statement.body.body.clone())?;
+        }
+
+        Ok(())
+    }
+
+    fn eval_block(&mut self, block: ASTNode) -> Result<(), Unwind> {
+        if let ASTNode::BlockStatement(BlockStatement { body }) = block {
+            self.eval_statement_list(*body)?;
         }
+        Ok(())
     }
 
-    fn validate_function_call(&self, func_call: &FunctionCall, func_expr: &FunctionExpression) {
+    fn validate_function_call(
+        &self,
+        func_call: &FunctionCall,
+        func_expr: &FunctionExpression,
+    ) -> Result<(), OrcaError> {
         if func_call.args.len() < func_expr.args.len() {
-            panic!(
-                "{} missing function args expected {} received {}",
-                func_expr.name,
-                func_expr.args.len(),
-                func_call.args.len()
-            )
-        }
-    }
-
-    fn push_function(&mut self, func_call: &FunctionCall, func_expr: &FunctionExpression) {
-        let mut args = vec![];
-        // evaluate any variables in args
-        for (arg_name, arg_value) in func_expr.args.iter().zip(func_call.args.iter()) {
-            let value = match arg_value {
-                Symbol::Variable(var_name) => self.symbol_table.get(var_name).clone(),
-                _ => arg_value.clone(),
-            };
-            args.push((arg_name, value));
+            return Err(OrcaError::MissingArgument {
+                function: func_expr.name.clone(),
+                expected: func_expr.args.len(),
+                received: func_call.args.len(),
+                span: None,
+            });
         }
+        Ok(())
+    }
+
+    fn push_function(
+        &mut self,
+        func_call: &FunctionCall,
+        func_expr: &FunctionExpression,
+    ) -> Result<(), OrcaError> {
+        let resolved = self.resolve_call_args(&func_call.args)?;
+        let args: Vec<_> = func_expr.args.iter().zip(resolved).collect();
 
         self.symbol_table.push_scope(&func_expr.name);
 
         for (arg_name, arg_value) in args {
             self.symbol_table.insert(arg_name, arg_value);
         }
+
+        Ok(())
+    }
+
+    fn resolve_call_args(&self, args: &[Symbol]) -> Result<Vec<Symbol>, OrcaError> {
+        let mut resolved = vec![];
+        for arg in args {
+            let value = match arg {
+                Symbol::Variable(name) => self.symbol_table.get(name)?.clone(),
+                other => other.clone(),
+            };
+            resolved.push(value);
+        }
+        Ok(resolved)
     }
 
-    fn eval_function_call(&mut self, func_call: FunctionCall) -> Option<Symbol> {
+    fn eval_function_call(&mut self, func_call: FunctionCall) -> Result<Option<Symbol>, Unwind> {
+        if let Some(native) = BUILTINS
+            .iter()
+            .find(|(name, _)| *name == func_call.name)
+            .map(|(_, native)| *native)
+        {
+            let args = self.resolve_call_args(&func_call.args)?;
+            return Ok(native(&args));
+        }
+
         let func_expr = match self.symbol_table.get(&func_call.name) {
-            Symbol::Function(f) => f.clone(),
-            _ => return None,
+            Ok(Symbol::Function(f)) => f.clone(),
+            _ => return Ok(None),
         };
 
-        self.validate_function_call(&func_call, &func_expr);
-        self.push_function(&func_call, &func_expr);
+        self.validate_function_call(&func_call, &func_expr)?;
+        self.push_function(&func_call, &func_expr)?;
+
+        let result = self.eval_statement_list(*func_expr.body);
+        self.symbol_table.pop_scope();
+
+        match result {
+            Ok(()) => Ok(None),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(err @ Unwind::Error(_)) => Err(err),
+        }
+    }
+
+    fn eval_command(&mut self, words: Vec<ASTNode>) -> Result<Option<Symbol>, Unwind> {
+        let stages = self.collect_pipeline_stages(words)?;
+        let status = self.run_pipeline(stages)?;
+        Ok(Some(Symbol::Number(status as f64)))
+    }
+
+    /// Flattens a (possibly piped) `Command` node into its argv/redirect per
+    /// stage, evaluating each word to the argument string the process will
+    /// see.
+    fn collect_pipeline_stages(
+        &mut self,
+        mut words: Vec<ASTNode>,
+    ) -> Result<Vec<CommandStage>, Unwind> {
+        let mut stages = vec![];
 
-        for line in *func_expr.body {
-            match line {
-                ASTNode::ReturnExpression(expr) => {
-                    let res = self.eval_node(*expr);
-                    self.symbol_table.pop_scope();
-                    return res;
+        loop {
+            let next_stage = match words.last() {
+                Some(ASTNode::Command(_)) => match words.pop() {
+                    Some(ASTNode::Command(inner)) => Some(*inner),
+                    _ => unreachable!(),
+                },
+                _ => None,
+            };
+
+            let redirect = match words.last() {
+                Some(ASTNode::Redirect(_)) => match words.pop() {
+                    Some(ASTNode::Redirect(target)) => match self.eval_node(*target)? {
+                        Some(Symbol::String(s)) => Some(s),
+                        Some(Symbol::Number(n)) => Some(n.to_string()),
+                        _ => None,
+                    },
+                    _ => unreachable!(),
+                },
+                _ => None,
+            };
+
+            let mut argv = vec![];
+            for word in words {
+                match self.eval_node(word)? {
+                    Some(Symbol::String(s)) => argv.push(s),
+                    Some(Symbol::Number(n)) => argv.push(n.to_string()),
+                    Some(Symbol::Boolean(b)) => argv.push(b.to_string()),
+                    _ => {}
                 }
-                _ => self.eval_node(line),
+            }
+
+            stages.push(CommandStage { argv, redirect });
+
+            match next_stage {
+                Some(next) => words = next,
+                None => break,
+            }
+        }
+
+        Ok(stages)
+    }
+
+    fn run_pipeline(&self, stages: Vec<CommandStage>) -> Result<i32, Unwind> {
+        let stage_count = stages.len();
+        let mut previous_stdout = None;
+        let mut children = vec![];
+
+        for (i, stage) in stages.into_iter().enumerate() {
+            let (program, args) = match stage.argv.split_first() {
+                Some((program, args)) => (program.clone(), args.to_vec()),
+                None => continue,
             };
+
+            let mut command = ProcessCommand::new(&program);
+            command.args(&args);
+            command.stdin(previous_stdout.take().map_or(Stdio::inherit(), Stdio::from));
+
+            if let Some(target) = &stage.redirect {
+                let file = File::create(target).map_err(|err| {
+                    OrcaError::CommandFailed {
+                        message: format!("failed to open '{}' for writing: {}", target, err),
+                        span: None,
+                    }
+                })?;
+                command.stdout(file);
+            } else if i == stage_count - 1 {
+                command.stdout(Stdio::inherit());
+            } else {
+                command.stdout(Stdio::piped());
+            }
+
+            let mut child = command.spawn().map_err(|err| OrcaError::CommandFailed {
+                message: format!("failed to spawn '{}': {}", program, err),
+                span: None,
+            })?;
+
+            previous_stdout = child.stdout.take();
+            children.push(child);
         }
 
-        self.symbol_table.pop_scope();
-        None
+        let mut status = 0;
+        for mut child in children {
+            let exit = child.wait().map_err(|err| OrcaError::CommandFailed {
+                message: format!("failed to wait on child process: {}", err),
+                span: None,
+            })?;
+            status = exit.code().unwrap_or(-1);
+        }
+
+        Ok(status)
     }
 
-    fn eval_variable_statement(&mut self, node: VariableExpression) {
-        if let Some(val) = self.eval_node(*node.value) {
+    fn eval_variable_statement(&mut self, node: VariableExpression) -> Result<(), Unwind> {
+        if let Some(val) = self.eval_node(*node.value)? {
             self.symbol_table.insert(&node.name, val);
         }
+        Ok(())
     }
 
-    fn eval_unary_expression(&mut self, node: ASTNode) -> Option<Symbol> {
-        let symbol = match self.eval_node(node) {
+    fn eval_unary_expression(&mut self, node: ASTNode) -> Result<Option<Symbol>, Unwind> {
+        let symbol = match self.eval_node(node)? {
             Some(s) => s,
-            None => return None,
+            None => return Ok(None),
         };
 
         match symbol {
-            Symbol::Number(num) => Some(Symbol::Number(-num)),
-            _ => None,
+            Symbol::Number(num) => Ok(Some(Symbol::Number(-num))),
+            _ => Ok(None),
         }
     }
 
-    fn eval_binary_expression(&mut self, be: BinaryExpression) -> Option<Symbol> {
-        let left_symbol = match self.eval_node(*be.left) {
+    fn eval_binary_expression(&mut self, be: BinaryExpression) -> Result<Option<Symbol>, Unwind> {
+        let left_symbol = match self.eval_node(*be.left)? {
             Some(s) => s,
-            None => return None,
+            None => return Ok(None),
         };
 
-        let right_symbol = match self.eval_node(*be.right) {
+        let right_symbol = match self.eval_node(*be.right)? {
             Some(s) => s,
-            None => return None,
+            None => return Ok(None),
         };
 
         let result_symbol = match be.operator {
@@ -164,59 +459,125 @@ impl ASTEvaluator {
             | TokenType::LessThan
             | TokenType::GreaterThanOrEqualTo
             | TokenType::LessThanOrEqualTo => {
-                self.compare(&left_symbol, &be.operator, &right_symbol)
+                self.compare(&left_symbol, &be.operator, &right_symbol)?
             }
-            _ => self.eval_math_expression(&left_symbol, &be.operator, &right_symbol),
+            _ => self.eval_math_expression(&left_symbol, &be.operator, &right_symbol)?,
         };
 
-        Some(result_symbol)
+        Ok(Some(result_symbol))
     }
 
-    fn eval_math_expression(&self, left: &Symbol, operator: &TokenType, right: &Symbol) -> Symbol {
+    fn eval_math_expression(
+        &self,
+        left: &Symbol,
+        operator: &TokenType,
+        right: &Symbol,
+    ) -> Result<Symbol, OrcaError> {
+        if let (Symbol::String(ls), Symbol::String(rs)) = (left, right) {
+            return match operator {
+                TokenType::Plus => Ok(Symbol::String(format!("{}{}", ls, rs))),
+                _ => Err(OrcaError::TypeMismatch {
+                    message: format!(
+                        "{:?} {:?} {:?}: only + is supported between strings",
+                        left, operator, right
+                    ),
+                    span: None,
+                }),
+            };
+        }
+
         let (l, r) = match (left, right) {
             (Symbol::Number(ln), Symbol::Number(rn)) => (ln, rn),
-            _ => panic!(
-                "{:?} {:?} {:?}: can only perform mathematical expressions on numbers",
-                left, operator, right
-            ),
+            _ => {
+                return Err(OrcaError::TypeMismatch {
+                    message: format!(
+                        "{:?} {:?} {:?}: can only perform mathematical expressions on numbers",
+                        left, operator, right
+                    ),
+                    span: None,
+                })
+            }
         };
 
+        if matches!(operator, TokenType::ForwardSlash | TokenType::Percent) && *r == 0.0 {
+            return Err(OrcaError::DivisionByZero { span: None });
+        }
+
         let res = match operator {
             TokenType::Plus => l + r,
             TokenType::Minus => l - r,
             TokenType::Asterisk => l * r,
             TokenType::ForwardSlash => l / r,
+            TokenType::Percent => l % r,
             TokenType::Carat => l.powf(*r),
-            _ => panic!("invalid operator {:?}", operator),
+            _ => {
+                return Err(OrcaError::TypeMismatch {
+                    message: format!("invalid operator {:?}", operator),
+                    span: None,
+                })
+            }
         };
 
-        Symbol::Number(res)
+        Ok(Symbol::Number(res))
     }
 
-    fn compare(&self, left: &Symbol, operator: &TokenType, right: &Symbol) -> Symbol {
+    fn compare(
+        &self,
+        left: &Symbol,
+        operator: &TokenType,
+        right: &Symbol,
+    ) -> Result<Symbol, OrcaError> {
         match (left, right) {
             (Symbol::Number(ln), Symbol::Number(rn)) => self.compare_number(*ln, operator, *rn),
             (Symbol::Boolean(lb), Symbol::Boolean(rb)) => match operator {
-                TokenType::DoubleEquals => Symbol::Boolean(lb == rb),
-                _ => panic!(
-                    "{:?} {:?} {:?}: unable to compare booleans",
-                    left, operator, right
-                ),
+                TokenType::DoubleEquals => Ok(Symbol::Boolean(lb == rb)),
+                _ => Err(OrcaError::TypeMismatch {
+                    message: format!(
+                        "{:?} {:?} {:?}: unable to compare booleans",
+                        left, operator, right
+                    ),
+                    span: None,
+                }),
+            },
+            (Symbol::String(ls), Symbol::String(rs)) => match operator {
+                TokenType::DoubleEquals => Ok(Symbol::Boolean(ls == rs)),
+                TokenType::LessThan => Ok(Symbol::Boolean(ls < rs)),
+                TokenType::GreaterThan => Ok(Symbol::Boolean(ls > rs)),
+                _ => Err(OrcaError::TypeMismatch {
+                    message: format!(
+                        "{:?} {:?} {:?}: unable to compare strings",
+                        left, operator, right
+                    ),
+                    span: None,
+                }),
             },
-            _ => panic!("{:?} {:?} {:?}: type mismatch", left, operator, right),
+            _ => Err(OrcaError::TypeMismatch {
+                message: format!("{:?} {:?} {:?}: type mismatch", left, operator, right),
+                span: None,
+            }),
         }
     }
 
-    fn compare_number(&self, left: f64, operator: &TokenType, right: f64) -> Symbol {
+    fn compare_number(
+        &self,
+        left: f64,
+        operator: &TokenType,
+        right: f64,
+    ) -> Result<Symbol, OrcaError> {
         let res = match operator {
             TokenType::DoubleEquals => left == right,
             TokenType::GreaterThan => left > right,
             TokenType::LessThan => left < right,
             TokenType::GreaterThanOrEqualTo => left >= right,
             TokenType::LessThanOrEqualTo => left <= right,
-            _ => panic!("expected a comparison"),
+            _ => {
+                return Err(OrcaError::TypeMismatch {
+                    message: "expected a comparison".to_string(),
+                    span: None,
+                })
+            }
         };
 
-        Symbol::Boolean(res)
+        Ok(Symbol::Boolean(res))
     }
 }
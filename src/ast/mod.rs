@@ -0,0 +1,4 @@
+pub mod ast;
+pub mod evaluator;
+pub mod symbol;
+pub mod symbol_table;
@@ -0,0 +1,10 @@
+use super::ast::FunctionExpression;
+
+#[derive(Debug, Clone)]
+pub enum Symbol {
+    Number(f64),
+    Boolean(bool),
+    String(String),
+    Variable(String),
+    Function(FunctionExpression),
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use crate::error::OrcaError;
+
+use super::symbol::Symbol;
+
+pub struct SymbolTable {
+    scopes: Vec<(String, HashMap<String, Symbol>)>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable {
+            scopes: vec![("global".to_string(), HashMap::new())],
+        }
+    }
+
+    pub fn push_scope(&mut self, name: &str) {
+        self.scopes.push((name.to_string(), HashMap::new()));
+    }
+
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, value: Symbol) {
+        let (_, scope) = self.scopes.last_mut().expect("at least the global scope");
+        scope.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Symbol, OrcaError> {
+        for (_, scope) in self.scopes.iter().rev() {
+            if let Some(value) = scope.get(name) {
+                return Ok(value);
+            }
+        }
+
+        Err(OrcaError::UndefinedVariable {
+            name: name.to_string(),
+            span: None,
+        })
+    }
+}
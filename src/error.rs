@@ -0,0 +1,126 @@
+use std::fmt;
+
+use crate::lexer::{Span, TokenType};
+
+#[derive(Debug, Clone)]
+pub enum OrcaError {
+    UnexpectedToken {
+        expected: String,
+        found: TokenType,
+        span: Span,
+    },
+    UndefinedVariable {
+        name: String,
+        span: Option<Span>,
+    },
+    TypeMismatch {
+        message: String,
+        span: Option<Span>,
+    },
+    DivisionByZero {
+        span: Option<Span>,
+    },
+    MissingArgument {
+        function: String,
+        expected: usize,
+        received: usize,
+        span: Option<Span>,
+    },
+    CommandFailed {
+        message: String,
+        span: Option<Span>,
+    },
+}
+
+impl OrcaError {
+    /// Fills in a span for an evaluator error raised below the statement that
+    /// is currently being evaluated, if one wasn't already attached closer to
+    /// the fault.
+    pub fn with_span(self, span: Span) -> OrcaError {
+        match self {
+            OrcaError::UndefinedVariable { name, span: None } => OrcaError::UndefinedVariable {
+                name,
+                span: Some(span),
+            },
+            OrcaError::TypeMismatch {
+                message,
+                span: None,
+            } => OrcaError::TypeMismatch {
+                message,
+                span: Some(span),
+            },
+            OrcaError::DivisionByZero { span: None } => {
+                OrcaError::DivisionByZero { span: Some(span) }
+            }
+            OrcaError::MissingArgument {
+                function,
+                expected,
+                received,
+                span: None,
+            } => OrcaError::MissingArgument {
+                function,
+                expected,
+                received,
+                span: Some(span),
+            },
+            OrcaError::CommandFailed {
+                message,
+                span: None,
+            } => OrcaError::CommandFailed {
+                message,
+                span: Some(span),
+            },
+            other => other,
+        }
+    }
+}
+
+fn format_span(span: &Option<Span>) -> String {
+    match span {
+        Some(span) => format!(" at {}", span),
+        None => String::new(),
+    }
+}
+
+impl fmt::Display for OrcaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrcaError::UnexpectedToken {
+                expected,
+                found,
+                span,
+            } => write!(
+                f,
+                "unexpected token {:?} at {}, expected {}",
+                found, span, expected
+            ),
+            OrcaError::UndefinedVariable { name, span } => {
+                write!(f, "undefined variable '{}'{}", name, format_span(span))
+            }
+            OrcaError::TypeMismatch { message, span } => {
+                write!(f, "type mismatch: {}{}", message, format_span(span))
+            }
+            OrcaError::DivisionByZero { span } => {
+                write!(f, "division by zero{}", format_span(span))
+            }
+            OrcaError::MissingArgument {
+                function,
+                expected,
+                received,
+                span,
+            } => write!(
+                f,
+                "{} missing function args expected {} received {}{}",
+                function,
+                expected,
+                received,
+                format_span(span)
+            ),
+            OrcaError::CommandFailed { message, span } => {
+                write!(f, "command failed: {}{}", message, format_span(span))
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrcaError {}
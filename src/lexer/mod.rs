@@ -0,0 +1,154 @@
+pub mod token;
+
+pub use token::{Span, Token, TokenType};
+
+pub struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Lexer {
+    pub fn new(src: &str) -> Lexer {
+        Lexer {
+            chars: src.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    pub fn next_token(&mut self) -> Token {
+        self.skip_whitespace();
+        let start = self.pos;
+        let kind = self.next_token_type();
+        Token {
+            kind,
+            span: Span::new(start, self.pos),
+        }
+    }
+
+    /// Peeks `n` tokens ahead of the token about to be returned by the next
+    /// call to `next_token`, without consuming any input.
+    pub fn lookahead(&self, n: usize) -> TokenType {
+        let mut lookahead_lexer = Lexer {
+            chars: self.chars.clone(),
+            pos: self.pos,
+        };
+
+        let mut token = lookahead_lexer.next_token();
+        for _ in 0..n {
+            token = lookahead_lexer.next_token();
+        }
+
+        token.kind
+    }
+
+    fn next_token_type(&mut self) -> TokenType {
+        let c = match self.peek_char() {
+            Some(c) => c,
+            None => return TokenType::EOF,
+        };
+
+        if c == '\n' {
+            self.pos += 1;
+            return TokenType::Newline;
+        }
+
+        if c == '"' {
+            return self.read_string();
+        }
+
+        if c.is_ascii_digit() {
+            return self.read_number();
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            return TokenType::Identifier(self.read_identifier());
+        }
+
+        self.pos += 1;
+        match c {
+            '+' => TokenType::Plus,
+            '-' => TokenType::Minus,
+            '*' => TokenType::Asterisk,
+            '/' => TokenType::ForwardSlash,
+            '^' => TokenType::Carat,
+            '%' => TokenType::Percent,
+            ',' => TokenType::Comma,
+            '(' => TokenType::OpenParenthesis,
+            ')' => TokenType::CloseParenthesis,
+            '{' => TokenType::OpenBraces,
+            '}' => TokenType::CloseBraces,
+            '$' => TokenType::Dollar,
+            '|' => TokenType::Pipe,
+            '.' => TokenType::Dot,
+            '=' => self.one_or_two('=', TokenType::Equals, TokenType::DoubleEquals),
+            '>' => self.one_or_two('=', TokenType::GreaterThan, TokenType::GreaterThanOrEqualTo),
+            '<' => self.one_or_two('=', TokenType::LessThan, TokenType::LessThanOrEqualTo),
+            _ => TokenType::Illegal(c),
+        }
+    }
+
+    fn one_or_two(&mut self, second: char, one: TokenType, two: TokenType) -> TokenType {
+        if self.peek_char() == Some(second) {
+            self.pos += 1;
+            two
+        } else {
+            one
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek_char(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn read_identifier(&mut self) -> String {
+        let start = self.pos;
+        while matches!(self.peek_char(), Some(c) if c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn read_number(&mut self) -> TokenType {
+        let start = self.pos;
+        while matches!(self.peek_char(), Some(c) if c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        if self.peek_char() == Some('.') {
+            self.pos += 1;
+            while matches!(self.peek_char(), Some(c) if c.is_ascii_digit()) {
+                self.pos += 1;
+            }
+
+            let text: String = self.chars[start..self.pos].iter().collect();
+            return TokenType::Decimal(text.parse().expect("lexed a valid decimal"));
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        TokenType::Integer(text.parse().expect("lexed a valid integer"))
+    }
+
+    /// Reads a `"..."` literal, assuming the opening quote is the current
+    /// character. Unterminated strings read to the end of input rather than
+    /// erroring, consistent with the other `read_*` helpers.
+    fn read_string(&mut self) -> TokenType {
+        self.pos += 1;
+        let start = self.pos;
+        while matches!(self.peek_char(), Some(c) if c != '"') {
+            self.pos += 1;
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        if self.peek_char() == Some('"') {
+            self.pos += 1;
+        }
+
+        TokenType::StringLiteral(text)
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenType {
+    Identifier(String),
+    Integer(i64),
+    Decimal(f64),
+    StringLiteral(String),
+    Illegal(char),
+
+    Plus,
+    Minus,
+    Asterisk,
+    ForwardSlash,
+    Carat,
+    Percent,
+
+    Equals,
+    DoubleEquals,
+    GreaterThan,
+    LessThan,
+    GreaterThanOrEqualTo,
+    LessThanOrEqualTo,
+
+    Comma,
+    OpenParenthesis,
+    CloseParenthesis,
+    OpenBraces,
+    CloseBraces,
+
+    Dollar,
+    Pipe,
+    Dot,
+
+    Newline,
+    EOF,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenType,
+    pub span: Span,
+}
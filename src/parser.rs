@@ -1,79 +1,99 @@
-use core::panic;
-
 use crate::{
     ast::{
-        ast::{ASTNode, BinaryExpression, FunctionCall, FunctionExpression, VariableExpression},
+        ast::{
+            ASTNode, BinaryExpression, BlockStatement, FunctionCall, FunctionExpression,
+            IfStatement, Node, VariableExpression, WhileStatement,
+        },
         symbol::Symbol,
     },
-    lexer::{self, TokenType},
+    error::OrcaError,
+    lexer::{self, Span, TokenType},
 };
 
 pub struct Parser {
     lexer: lexer::Lexer,
     curr_token: TokenType,
+    curr_span: Span,
+    prev_span: Span,
 }
 
 impl Parser {
     pub fn new(src: &str) -> Parser {
         let mut lexer = lexer::Lexer::new(src);
-        let curr_token = lexer.next_token();
-        Parser { lexer, curr_token }
+        let token = lexer.next_token();
+        Parser {
+            lexer,
+            curr_token: token.kind,
+            curr_span: token.span,
+            prev_span: token.span,
+        }
     }
 
-    pub fn parse(&mut self) -> ASTNode {
+    pub fn parse(&mut self) -> Result<ASTNode, OrcaError> {
         self.program()
     }
 
-    fn eat_number(&mut self) -> TokenType {
+    fn unexpected(&self, expected: &str) -> OrcaError {
+        OrcaError::UnexpectedToken {
+            expected: expected.to_string(),
+            found: self.curr_token.clone(),
+            span: self.curr_span,
+        }
+    }
+
+    fn eat_number(&mut self) -> Result<TokenType, OrcaError> {
         match self.curr_token {
             TokenType::Decimal(_) | TokenType::Integer(_) => self.eat(&self.curr_token.clone()),
-            _ => panic!("unexpected token {:?}, expected a number", self.curr_token),
+            _ => Err(self.unexpected("a number")),
         }
     }
 
-    fn eat_operator(&mut self) -> TokenType {
+    fn eat_operator(&mut self) -> Result<TokenType, OrcaError> {
         match self.curr_token {
             TokenType::Plus
             | TokenType::Minus
             | TokenType::Asterisk
             | TokenType::ForwardSlash
-            | TokenType::Carat => self.eat(&self.curr_token.clone()),
-            _ => panic!(
-                "unexpected token {:?}, expected an operator",
-                self.curr_token
-            ),
+            | TokenType::Carat
+            | TokenType::Percent
+            | TokenType::DoubleEquals
+            | TokenType::GreaterThan
+            | TokenType::LessThan
+            | TokenType::GreaterThanOrEqualTo
+            | TokenType::LessThanOrEqualTo => self.eat(&self.curr_token.clone()),
+            _ => Err(self.unexpected("an operator")),
         }
     }
 
-    fn eat_identifier(&mut self) -> String {
+    fn eat_identifier(&mut self) -> Result<String, OrcaError> {
         let curr_token = self.curr_token.clone();
         match &curr_token {
             TokenType::Identifier(ident) => {
-                self.eat(&curr_token);
-                ident.clone()
+                let ident = ident.clone();
+                self.eat(&curr_token)?;
+                Ok(ident)
             }
-            _ => panic!(
-                "unexpected token {:?}, expected an identifier",
-                self.curr_token
-            ),
+            _ => Err(self.unexpected("an identifier")),
         }
     }
 
-    fn eat(&mut self, expected_token: &TokenType) -> TokenType {
+    fn eat(&mut self, expected_token: &TokenType) -> Result<TokenType, OrcaError> {
         if self.curr_token == TokenType::EOF {
-            panic!("eof")
+            return Err(self.unexpected(&format!("{:?}", expected_token)));
         }
 
         if expected_token != &self.curr_token {
-            panic!(
-                "unexpected token {:?}, expected {:?}",
-                self.curr_token, expected_token
-            )
+            return Err(self.unexpected(&format!("{:?}", expected_token)));
         }
 
         let previous_token = self.curr_token.clone();
-        self.curr_token = self.lexer.next_token();
-        return previous_token;
+        self.prev_span = self.curr_span;
+
+        let token = self.lexer.next_token();
+        self.curr_token = token.kind;
+        self.curr_span = token.span;
+
+        Ok(previous_token)
     }
 
     fn get_precedence(&self, operator: &TokenType) -> usize {
@@ -81,46 +101,72 @@ impl Parser {
             &TokenType::Carat => 5,
             &TokenType::Asterisk => 3,
             &TokenType::ForwardSlash => 3,
+            &TokenType::Percent => 3,
             &TokenType::Plus => 2,
             &TokenType::Minus => 2,
+            &TokenType::DoubleEquals => 1,
+            &TokenType::GreaterThan => 1,
+            &TokenType::LessThan => 1,
+            &TokenType::GreaterThanOrEqualTo => 1,
+            &TokenType::LessThanOrEqualTo => 1,
             _ => 0,
         }
     }
 
     /**
      * Program
-     *    = statement_list
+     *    = statement+
      */
-    fn program(&mut self) -> ASTNode {
-        let statement_list = self.statement_list();
-        ASTNode::Program(Box::new(statement_list))
+    fn program(&mut self) -> Result<ASTNode, OrcaError> {
+        let statements = self.spanned_statements(|token| *token == TokenType::EOF)?;
+        Ok(ASTNode::Program(Box::new(statements)))
     }
 
     /**
      * statement_list
      *    = statement+
      */
-    fn statement_list(&mut self) -> Vec<ASTNode> {
+    fn statement_list(&mut self) -> Result<Vec<Node<ASTNode>>, OrcaError> {
+        self.spanned_statements(|token| *token == TokenType::EOF || *token == TokenType::CloseBraces)
+    }
+
+    /// Parses statements up to (but not consuming) a token satisfying `is_end`,
+    /// wrapping each in its own span so an evaluator error deep inside a
+    /// block (an `if`/`while` body, a function body) can be blamed on the
+    /// statement that actually failed instead of the enclosing top-level one.
+    fn spanned_statements(
+        &mut self,
+        is_end: impl Fn(&TokenType) -> bool,
+    ) -> Result<Vec<Node<ASTNode>>, OrcaError> {
         let mut statements = vec![];
 
-        while self.curr_token != TokenType::EOF && self.curr_token != TokenType::CloseBraces {
-            statements.push(self.statement());
+        while !is_end(&self.curr_token) {
+            let start = self.curr_span;
+            let statement = self.statement()?;
+            let span = Span::new(start.start, self.prev_span.end);
+            statements.push(Node {
+                inner: statement,
+                span,
+            });
 
             if self.curr_token != TokenType::EOF {
-                self.eat(&TokenType::Newline);
+                self.eat(&TokenType::Newline)?;
             }
         }
 
-        return statements;
+        Ok(statements)
     }
 
     /**
      * statement
      *   = variable_expression
      *   / function_expression
+     *   / if_statement
+     *   / while_statement
+     *   / command_statement
      *   / expression
      */
-    fn statement(&mut self) -> ASTNode {
+    fn statement(&mut self) -> Result<ASTNode, OrcaError> {
         if self.lexer.lookahead(0) == TokenType::Equals {
             return self.variable_expression();
         }
@@ -129,39 +175,139 @@ impl Parser {
             return self.function_expression();
         }
 
+        if self.curr_token == TokenType::Identifier("if".to_string()) {
+            return self.if_statement();
+        }
+
+        if self.curr_token == TokenType::Identifier("while".to_string()) {
+            return self.while_statement();
+        }
+
+        if self.curr_token == TokenType::Dollar {
+            return self.command_statement();
+        }
+
         self.expression(0)
     }
 
+    /**
+     * command_statement
+     *   = "$" command
+     */
+    fn command_statement(&mut self) -> Result<ASTNode, OrcaError> {
+        self.eat(&TokenType::Dollar)?;
+        self.command()
+    }
+
+    /**
+     * command
+     *   = command_word+ (">" command_word)? ("|" command)?
+     */
+    fn command(&mut self) -> Result<ASTNode, OrcaError> {
+        let mut words = vec![self.command_word()?];
+
+        while self.is_command_word_start() {
+            words.push(self.command_word()?);
+        }
+
+        if self.curr_token == TokenType::GreaterThan {
+            self.eat(&TokenType::GreaterThan)?;
+            words.push(ASTNode::Redirect(Box::new(self.command_word()?)));
+        }
+
+        if self.curr_token == TokenType::Pipe {
+            self.eat(&TokenType::Pipe)?;
+            words.push(self.command()?);
+        }
+
+        Ok(ASTNode::Command(Box::new(words)))
+    }
+
+    fn is_command_word_start(&self) -> bool {
+        matches!(
+            self.curr_token,
+            TokenType::Identifier(_)
+                | TokenType::Integer(_)
+                | TokenType::Decimal(_)
+                | TokenType::Dollar
+        )
+    }
+
+    /**
+     * command_word
+     *   = "$" identifier
+     *   / command_word_segment ("." command_word_segment)*
+     */
+    fn command_word(&mut self) -> Result<ASTNode, OrcaError> {
+        if self.curr_token == TokenType::Dollar {
+            self.eat(&TokenType::Dollar)?;
+            return Ok(ASTNode::Variable(self.eat_identifier()?));
+        }
+
+        let mut word = self.command_word_segment()?;
+
+        // A bare `out.txt` redirect target lexes as three tokens
+        // (`out`, `.`, `txt`); stitch adjacent ones back into a single
+        // word here rather than teaching the lexer that `.` belongs to
+        // every identifier in the language.
+        while self.curr_token == TokenType::Dot && self.prev_span.end == self.curr_span.start {
+            self.eat(&TokenType::Dot)?;
+            word.push('.');
+            word.push_str(&self.command_word_segment()?);
+        }
+
+        Ok(ASTNode::String(word))
+    }
+
+    /**
+     * command_word_segment
+     *   = identifier
+     *   / NUMBER
+     */
+    fn command_word_segment(&mut self) -> Result<String, OrcaError> {
+        match self.curr_token.clone() {
+            TokenType::Identifier(word) => {
+                self.eat(&TokenType::Identifier(word.clone()))?;
+                Ok(word)
+            }
+            _ => match self.eat_number()? {
+                TokenType::Integer(value) => Ok(value.to_string()),
+                TokenType::Decimal(value) => Ok(value.to_string()),
+                _ => Err(self.unexpected("a command word")),
+            },
+        }
+    }
+
     /**
      * expression
      *  = prefix (infix)*
      */
-    fn expression(&mut self, precedence: usize) -> ASTNode {
-        let mut left = self.prefix();
+    fn expression(&mut self, precedence: usize) -> Result<ASTNode, OrcaError> {
+        let mut left = self.prefix()?;
 
         while self.curr_token != TokenType::EOF
             && self.curr_token != TokenType::Newline
             && precedence < self.get_precedence(&self.curr_token)
         {
-            left = self.infix(left, &self.curr_token.clone())
+            left = self.infix(left, &self.curr_token.clone())?;
         }
 
-        left
+        Ok(left)
     }
 
     /**
      * variable_expression
      *   = identifier "=" expression
      */
-    fn variable_expression(&mut self) -> ASTNode {
-        let name = self.eat_identifier();
-        self.eat(&TokenType::Equals);
-        let expression = self.expression(0);
+    fn variable_expression(&mut self) -> Result<ASTNode, OrcaError> {
+        let name = self.eat_identifier()?;
+        self.eat(&TokenType::Equals)?;
+        let expression = self.expression(0)?;
 
-        ASTNode::VariableExpression(VariableExpression {
+        Ok(ASTNode::VariableExpression(VariableExpression {
             name,
             value: Box::new(expression),
-        })
+        }))
     }
 
     /**
@@ -170,21 +316,89 @@ impl Parser {
      *         expression
      *     "}"
      */
-    fn function_expression(&mut self) -> ASTNode {
-        self.eat(&TokenType::Identifier("func".to_string()));
-        let name = self.eat_identifier();
-        self.eat(&TokenType::OpenParenthesis);
-        let func_args = self.function_expression_args();
-        self.eat(&TokenType::CloseParenthesis);
-        self.eat(&TokenType::OpenBraces);
-        self.eat(&TokenType::Newline);
-        let statement_list = self.statement_list();
-        self.eat(&TokenType::CloseBraces);
-
-        ASTNode::FunctionExpression(FunctionExpression {
+    fn function_expression(&mut self) -> Result<ASTNode, OrcaError> {
+        self.eat(&TokenType::Identifier("func".to_string()))?;
+        let name = self.eat_identifier()?;
+        self.eat(&TokenType::OpenParenthesis)?;
+        let func_args = self.function_expression_args()?;
+        self.eat(&TokenType::CloseParenthesis)?;
+        self.eat(&TokenType::OpenBraces)?;
+        self.eat(&TokenType::Newline)?;
+        let statement_list = self.statement_list()?;
+        self.eat(&TokenType::CloseBraces)?;
+
+        Ok(ASTNode::FunctionExpression(FunctionExpression {
             name,
             body: Box::new(statement_list),
             args: func_args,
+        }))
+    }
+
+    /**
+     * if_statement
+     *   = "if" expression "{" statement_list "}" else_clause?
+     */
+    fn if_statement(&mut self) -> Result<ASTNode, OrcaError> {
+        self.eat(&TokenType::Identifier("if".to_string()))?;
+        let condition = self.expression(0)?;
+        self.eat(&TokenType::OpenBraces)?;
+        self.eat(&TokenType::Newline)?;
+        let consequence = ASTNode::BlockStatement(self.block_statement()?);
+        let alternative = self.else_clause()?;
+
+        Ok(ASTNode::IfStatement(IfStatement {
+            condition: Box::new(condition),
+            consequence: Box::new(consequence),
+            alternative: alternative.map(Box::new),
+        }))
+    }
+
+    /**
+     * else_clause
+     *   = "else" (if_statement / ("{" statement_list "}"))
+     */
+    fn else_clause(&mut self) -> Result<Option<ASTNode>, OrcaError> {
+        if self.curr_token != TokenType::Identifier("else".to_string()) {
+            return Ok(None);
+        }
+        self.eat(&TokenType::Identifier("else".to_string()))?;
+
+        if self.curr_token == TokenType::Identifier("if".to_string()) {
+            return Ok(Some(self.if_statement()?));
+        }
+
+        self.eat(&TokenType::OpenBraces)?;
+        self.eat(&TokenType::Newline)?;
+        Ok(Some(ASTNode::BlockStatement(self.block_statement()?)))
+    }
+
+    /**
+     * while_statement
+     *   = "while" expression "{" statement_list "}"
+     */
+    fn while_statement(&mut self) -> Result<ASTNode, OrcaError> {
+        self.eat(&TokenType::Identifier("while".to_string()))?;
+        let condition = self.expression(0)?;
+        self.eat(&TokenType::OpenBraces)?;
+        self.eat(&TokenType::Newline)?;
+        let body = self.block_statement()?;
+
+        Ok(ASTNode::WhileStatement(WhileStatement {
+            condition: Box::new(condition),
+            body: Box::new(body),
+        }))
+    }
+
+    /**
+     * block_statement
+     *   = statement_list "}"
+     */
+    fn block_statement(&mut self) -> Result<BlockStatement, OrcaError> {
+        let statements = self.statement_list()?;
+        self.eat(&TokenType::CloseBraces)?;
+
+        Ok(BlockStatement {
+            body: Box::new(statements),
         })
     }
 
@@ -192,21 +406,21 @@ impl Parser {
      * function_expression_args
      *   = (identifier,)*
      */
-    fn function_expression_args(&mut self) -> Vec<String> {
+    fn function_expression_args(&mut self) -> Result<Vec<String>, OrcaError> {
         if self.curr_token == TokenType::CloseParenthesis {
-            return vec![];
+            return Ok(vec![]);
         }
 
         let mut args = vec![];
         loop {
-            args.push(self.eat_identifier());
+            args.push(self.eat_identifier()?);
             if self.curr_token == TokenType::CloseParenthesis {
                 break;
             }
 
-            self.eat(&TokenType::Comma);
+            self.eat(&TokenType::Comma)?;
         }
-        args
+        Ok(args)
     }
 
     /**
@@ -214,16 +428,22 @@ impl Parser {
      *    = parenthesized_expression
      *    / unary_expression
      *    / return_expression
+     *    / boolean_literal
+     *    / string_literal
      *    / NUMBER
      */
-    fn prefix(&mut self) -> ASTNode {
+    fn prefix(&mut self) -> Result<ASTNode, OrcaError> {
         match &self.curr_token {
             TokenType::OpenParenthesis => return self.parenthesized_expression(),
             TokenType::Minus => return self.unary_expression(),
+            TokenType::StringLiteral(_) => return self.string_literal(),
             TokenType::Identifier(ident) => {
                 if ident == "return" {
                     return self.return_expression();
                 }
+                if ident == "true" || ident == "false" {
+                    return self.boolean_literal();
+                }
                 if self.lexer.lookahead(0) == TokenType::OpenParenthesis {
                     return self.function_call();
                 }
@@ -232,19 +452,47 @@ impl Parser {
             _ => (),
         };
 
-        match self.eat_number() {
-            TokenType::Decimal(value) => ASTNode::Number(value),
-            TokenType::Integer(value) => ASTNode::Number(value as f64),
-            _ => panic!("invalid prefix"),
+        match self.eat_number()? {
+            TokenType::Decimal(value) => Ok(ASTNode::Number(value)),
+            TokenType::Integer(value) => Ok(ASTNode::Number(value as f64)),
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    /**
+     * string_literal
+     *    = STRING
+     */
+    fn string_literal(&mut self) -> Result<ASTNode, OrcaError> {
+        match self.curr_token.clone() {
+            TokenType::StringLiteral(value) => {
+                self.eat(&TokenType::StringLiteral(value.clone()))?;
+                Ok(ASTNode::String(value))
+            }
+            _ => Err(self.unexpected("a string")),
+        }
+    }
+
+    /**
+     * boolean_literal
+     *    = "true" / "false"
+     */
+    fn boolean_literal(&mut self) -> Result<ASTNode, OrcaError> {
+        match self.curr_token.clone() {
+            TokenType::Identifier(ident) if ident == "true" || ident == "false" => {
+                self.eat(&TokenType::Identifier(ident.clone()))?;
+                Ok(ASTNode::Boolean(ident == "true"))
+            }
+            _ => Err(self.unexpected("a boolean")),
         }
     }
 
     /**
      * infix
-     *    = ("+" / "-" / "*" / "/" / "^") expression
+     *    = ("+" / "-" / "*" / "/" / "%" / "^" / "==" / ">" / "<" / ">=" / "<=") expression
      */
-    fn infix(&mut self, left: ASTNode, operator: &TokenType) -> ASTNode {
-        self.eat_operator();
+    fn infix(&mut self, left: ASTNode, operator: &TokenType) -> Result<ASTNode, OrcaError> {
+        self.eat_operator()?;
 
         let operator_precedence = self.get_precedence(operator);
         let precedence = if operator == &TokenType::Carat {
@@ -253,90 +501,98 @@ impl Parser {
             operator_precedence
         };
 
-        ASTNode::BinaryExpression(BinaryExpression {
+        Ok(ASTNode::BinaryExpression(BinaryExpression {
             left: Box::new(left),
             operator: operator.clone(),
-            right: Box::new(self.expression(precedence)),
-        })
+            right: Box::new(self.expression(precedence)?),
+        }))
     }
 
     /**
      * Variable
      *    = IDENTIFIER
      */
-    fn variable_statement(&mut self) -> ASTNode {
-        let name = self.eat_identifier();
-        ASTNode::Variable(name)
+    fn variable_statement(&mut self) -> Result<ASTNode, OrcaError> {
+        let name = self.eat_identifier()?;
+        Ok(ASTNode::Variable(name))
     }
 
     /**
      * parenthesized_expression
      *    = "(" expression ")"
      */
-    fn parenthesized_expression(&mut self) -> ASTNode {
-        self.eat(&TokenType::OpenParenthesis);
-        let expression = self.expression(0);
-        self.eat(&TokenType::CloseParenthesis);
-        expression
+    fn parenthesized_expression(&mut self) -> Result<ASTNode, OrcaError> {
+        self.eat(&TokenType::OpenParenthesis)?;
+        let expression = self.expression(0)?;
+        self.eat(&TokenType::CloseParenthesis)?;
+        Ok(expression)
     }
 
     /**
      * return_expression
      *    = "return" expression
      */
-    fn return_expression(&mut self) -> ASTNode {
-        self.eat(&TokenType::Identifier("return".to_string()));
-        let expression = self.expression(0);
-        ASTNode::ReturnExpression(Box::new(expression))
+    fn return_expression(&mut self) -> Result<ASTNode, OrcaError> {
+        self.eat(&TokenType::Identifier("return".to_string()))?;
+        let expression = self.expression(0)?;
+        Ok(ASTNode::ReturnExpression(Box::new(expression)))
     }
 
     /**
      * function_call
      *    = identifier "(" function_call_args ")"
      */
-    fn function_call(&mut self) -> ASTNode {
-        let fname = self.eat_identifier();
-        self.eat(&TokenType::OpenParenthesis);
-        let args = self.function_call_args();
-        self.eat(&TokenType::CloseParenthesis);
-        ASTNode::FunctionCall(FunctionCall { name: fname, args })
+    fn function_call(&mut self) -> Result<ASTNode, OrcaError> {
+        let fname = self.eat_identifier()?;
+        self.eat(&TokenType::OpenParenthesis)?;
+        let args = self.function_call_args()?;
+        self.eat(&TokenType::CloseParenthesis)?;
+        Ok(ASTNode::FunctionCall(FunctionCall { name: fname, args }))
     }
 
     /**
      * function_call_args
-     *   = ((identifier | NUMBER),)*
+     *   = ((identifier | "-"? NUMBER),)*
      */
-    fn function_call_args(&mut self) -> Vec<Symbol> {
+    fn function_call_args(&mut self) -> Result<Vec<Symbol>, OrcaError> {
         if self.curr_token == TokenType::CloseParenthesis {
-            return vec![];
+            return Ok(vec![]);
         }
 
         let mut args = vec![];
         loop {
             match &self.curr_token {
-                TokenType::Identifier(_) => args.push(Symbol::Variable(self.eat_identifier())),
-                _ => match self.eat_number() {
+                TokenType::Identifier(_) => args.push(Symbol::Variable(self.eat_identifier()?)),
+                TokenType::Minus => {
+                    self.eat(&TokenType::Minus)?;
+                    match self.eat_number()? {
+                        TokenType::Decimal(value) => args.push(Symbol::Number(-value)),
+                        TokenType::Integer(value) => args.push(Symbol::Number(-(value as f64))),
+                        _ => return Err(self.unexpected("a function argument")),
+                    }
+                }
+                _ => match self.eat_number()? {
                     TokenType::Decimal(value) => args.push(Symbol::Number(value)),
                     TokenType::Integer(value) => args.push(Symbol::Number(value as f64)),
-                    _ => panic!("invalid function argument"),
+                    _ => return Err(self.unexpected("a function argument")),
                 },
             };
             if self.curr_token == TokenType::CloseParenthesis {
                 break;
             }
 
-            self.eat(&TokenType::Comma);
+            self.eat(&TokenType::Comma)?;
         }
 
-        args
+        Ok(args)
     }
 
     /**
      * unary_expression
      *    = "-" expression
      */
-    fn unary_expression(&mut self) -> ASTNode {
-        self.eat(&TokenType::Minus);
-        ASTNode::UnaryExpression(Box::new(self.expression(4)))
+    fn unary_expression(&mut self) -> Result<ASTNode, OrcaError> {
+        self.eat(&TokenType::Minus)?;
+        Ok(ASTNode::UnaryExpression(Box::new(self.expression(4)?)))
     }
 }
@@ -1,13 +1,17 @@
-use orca::{ast::evaluator::ASTEvaluator, parser::Parser};
+use orca::{
+    ast::{ast::ASTNode, evaluator::ASTEvaluator, symbol::Symbol},
+    error::OrcaError,
+    parser::Parser,
+};
 
 fn assert_expr(expr: &str, expected: f64) {
     let mut evaluator = ASTEvaluator::new();
     let mut acc = 0.0;
 
-    let program = Parser::new(expr).parse();
-    for option in evaluator.eval(program) {
-        if let Some(value) = option {
-            acc += value;
+    let program = Parser::new(expr).parse().expect("parses");
+    for option in evaluator.eval(program).expect("evaluates") {
+        if let Some(Symbol::Number(n)) = option {
+            acc += n;
         }
     }
 
@@ -42,4 +46,83 @@ mod tests {
     fn functions() {
         assert_expr("func foo() {\nx = 1\n}", 0.0)
     }
+
+    #[test]
+    fn if_statements() {
+        assert_expr("if 1 {\nx = 2\n}\nx", 2.0);
+        assert_expr("if 0 {\nx = 2\n} else {\nx = 3\n}\nx", 3.0);
+        assert_expr("if 0 {\nx = 1\n} else if 1 {\nx = 2\n} else {\nx = 3\n}\nx", 2.0);
+    }
+
+    #[test]
+    fn while_statements() {
+        assert_expr("i = 0\nwhile i < 5 {\ni = i + 1\n}\ni", 5.0);
+    }
+
+    #[test]
+    fn return_unwinds_out_of_nested_blocks() {
+        assert_expr(
+            "func absolute(x) {\nif x < 0 {\nreturn -x\n}\nreturn x\n}\nabsolute(-3)",
+            3.0,
+        );
+        assert_expr(
+            "func first_positive(x) {\nwhile 1 {\nif x > 0 {\nreturn x\n}\nx = x + 1\n}\n}\nfirst_positive(-2)",
+            1.0,
+        );
+    }
+
+    #[test]
+    fn builtin_functions_cover_numeric_helpers() {
+        assert_expr("sqrt(16) + abs(3)", 7.0);
+    }
+
+    #[test]
+    fn modulo_operator() {
+        assert_expr("10 % 3", 1.0);
+    }
+
+    #[test]
+    fn strings_and_booleans_support_arithmetic_and_comparison() {
+        let program = Parser::new("\"foo\" + \"bar\" == \"foobar\"")
+            .parse()
+            .expect("parses");
+        let result = ASTEvaluator::new().eval(program).expect("evaluates");
+        assert!(matches!(result[0], Some(Symbol::Boolean(true))));
+
+        let program = Parser::new("true").parse().expect("parses");
+        let result = ASTEvaluator::new().eval(program).expect("evaluates");
+        assert!(matches!(result[0], Some(Symbol::Boolean(true))));
+
+        let program = Parser::new("\"a\" < \"b\"").parse().expect("parses");
+        let result = ASTEvaluator::new().eval(program).expect("evaluates");
+        assert!(matches!(result[0], Some(Symbol::Boolean(true))));
+    }
+
+    #[test]
+    fn command_pipelines_parse_as_command_nodes() {
+        let program = Parser::new("$ls | grep foo > out.txt")
+            .parse()
+            .expect("parses");
+
+        let ASTNode::Program(statements) = program else {
+            panic!("expected a program");
+        };
+        assert_eq!(1, statements.len());
+        assert!(matches!(statements[0].inner, ASTNode::Command(_)));
+    }
+
+    #[test]
+    fn parse_errors_do_not_panic() {
+        let err = Parser::new("1 +").parse().unwrap_err();
+        assert!(matches!(err, OrcaError::UnexpectedToken { .. }));
+    }
+
+    #[test]
+    fn eval_errors_do_not_panic() {
+        let program = Parser::new("func f() {\nx = 1\n}\nf + 1")
+            .parse()
+            .expect("parses");
+        let err = ASTEvaluator::new().eval(program).unwrap_err();
+        assert!(matches!(err, OrcaError::TypeMismatch { .. }));
+    }
 }
\ No newline at end of file